@@ -3,20 +3,48 @@
 //! This is about as raw an implementation as it gets - it's a thin, blocking I/O layer over Rust's
 //! built-in subprocess tools. It might be the simplest version of "expect" I've yet seen aside
 //! from some Bash scripts.
+use std::fs::File;
 use std::io::{Error, ErrorKind};
 use std::io::prelude::*;
-use std::process::{Command, Stdio, Child};
+use std::os::unix::io::FromRawFd;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio, Child, ChildStdin, ExitStatus};
 use std::result::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
 use std::time::Instant;
 
-use regex::RegexSet;
+use regex::Regex;
 
-// Rexport this for calling programs.
+// Rexport these for calling programs.
 pub use std::time::Duration;
+pub use std::os::unix::process::ExitStatusExt;
+
+/// How large a chunk the background reader asks for on each read.
+const READ_CHUNK: usize = 4096;
+
+/// The stream used to write to the child.
+enum Writer {
+    Piped(ChildStdin),
+    Pty(File),
+}
+
+/// Bytes accumulated from the child so far, shared between `Process` and its background reader.
+struct Buffer {
+    bytes: Vec<u8>,
+    eof: bool,
+}
 
 /// Provids necessary lifetime management for subprocess resources.
 pub struct Process {
     child: Child,
+    writer: Writer,
+    buffer: Arc<(Mutex<Buffer>, Condvar)>,
+    /// `None` in pty mode, where stderr is already the same stream as stdout.
+    stderr: Option<Arc<(Mutex<Buffer>, Condvar)>>,
+    merge_stderr: Arc<AtomicBool>,
+    strip_ansi: bool,
 }
 
 impl Process {
@@ -36,17 +64,147 @@ impl Process {
     /// Process::new("nope-i-don't-exist", vec![]).expect("This should fail.");
     /// ```
     pub fn new(exe: &str, args: Vec<&str>) -> Result<Process, Error> {
-        let command = Command::new(exe)
+        let mut child = Command::new(exe)
             .args(&args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn();
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr_stream = child.stderr.take().unwrap();
+
+        let buffer = spawn_reader(stdout);
+        let stderr = new_buffer();
+        let merge_stderr = Arc::new(AtomicBool::new(false));
+        spawn_stderr_reader(stderr_stream, stderr.clone(), buffer.clone(), merge_stderr.clone());
+
+        Ok(Process {
+            child,
+            writer: Writer::Piped(stdin),
+            buffer,
+            stderr: Some(stderr),
+            merge_stderr,
+            strip_ansi: false,
+        })
+    }
+
+    /// Starts a subprocess behind a pseudo-terminal instead of plain pipes.
+    ///
+    /// The child becomes the session leader of a new session, with the pty's slave side set as
+    /// its controlling terminal on fds 0/1/2. From the child's perspective this looks exactly
+    /// like an interactive terminal session, so programs that branch on `isatty` (most shells,
+    /// `ftp`, anything that wants line-editing or disables its own output buffering for a tty)
+    /// behave the way they would for a human at a keyboard.
+    ///
+    /// `emit`/`expect` transparently read and write the pty master fd in place of the piped
+    /// stdio used by [new](#method.new).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use reckon::base::Process;
+    /// Process::new_pty("true", vec![]).expect("Your UNIX is broken.");
+    /// ```
+    pub fn new_pty(exe: &str, args: Vec<&str>) -> Result<Process, Error> {
+        let mut master: libc::c_int = -1;
+        let mut slave: libc::c_int = -1;
 
-        match command {
-            Ok(c) => Ok(Process { child: c }),
-            Err(v) => Err(v),
+        let rc = unsafe {
+            libc::openpty(&mut master,
+                          &mut slave,
+                          std::ptr::null_mut(),
+                          std::ptr::null_mut(),
+                          std::ptr::null_mut())
+        };
+        if rc != 0 {
+            return Err(Error::last_os_error());
         }
+
+        let mut command = Command::new(exe);
+        command.args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        // Safety: `pre_exec` runs in the forked child, after fork but before exec, so it's the
+        // only place we can make the slave our controlling tty before the target program starts
+        // reading from fd 0.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setsid() < 0 {
+                    return Err(Error::last_os_error());
+                }
+                if libc::ioctl(slave, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(Error::last_os_error());
+                }
+                if libc::dup2(slave, 0) < 0 || libc::dup2(slave, 1) < 0 ||
+                   libc::dup2(slave, 2) < 0 {
+                    return Err(Error::last_os_error());
+                }
+                if slave > 2 {
+                    libc::close(slave);
+                }
+                libc::close(master);
+                Ok(())
+            });
+        }
+
+        let child = match command.spawn() {
+            Ok(c) => c,
+            Err(e) => {
+                unsafe {
+                    libc::close(master);
+                    libc::close(slave);
+                }
+                return Err(e);
+            }
+        };
+
+        // The parent doesn't need the slave fd; the child has its own copy via dup2.
+        unsafe {
+            libc::close(slave);
+        }
+
+        let master = unsafe { File::from_raw_fd(master) };
+        let writer_master = master.try_clone()?;
+        let buffer = spawn_reader(master);
+
+        Ok(Process {
+            child,
+            writer: Writer::Pty(writer_master),
+            buffer,
+            stderr: None,
+            merge_stderr: Arc::new(AtomicBool::new(false)),
+            strip_ansi: false,
+        })
+    }
+
+    /// Toggles whether `expect` strips ANSI/CSI escape sequences (cursor movement, color codes,
+    /// etc.) out of the buffered text before matching against it.
+    ///
+    /// This is off by default, so existing callers see exactly the bytes the child wrote, as
+    /// before. Turn it on when matching against a real terminal program (most things spawned via
+    /// [new_pty](#method.new_pty) qualify), since those escape sequences routinely land in the
+    /// middle of the text you're trying to match.
+    ///
+    /// ```rust
+    /// # use reckon::base::Process;
+    /// let mut p = Process::new("cat", vec![]).unwrap();
+    /// p.set_strip_ansi(true);
+    /// ```
+    pub fn set_strip_ansi(&mut self, strip: bool) {
+        self.strip_ansi = strip;
+    }
+
+    /// Toggles whether the child's stderr is interleaved into the same buffer `expect` matches
+    /// against, in addition to being available on its own via [expect_stderr](#method.expect_stderr).
+    ///
+    /// Off by default, so `expect` only ever sees stdout, as before. Has no effect in pty mode,
+    /// where stderr is already the same stream as stdout (both fds point at the same slave).
+    pub fn set_merge_stderr(&mut self, merge: bool) {
+        self.merge_stderr.store(merge, Ordering::Relaxed);
     }
 
     /// Writes some data to the subprocess.
@@ -66,8 +224,15 @@ impl Process {
     /// # assert_eq!(m, 0);
     /// ```
     pub fn emit(&mut self, data: &str) -> Result<(), Error> {
-        let mut stdin = self.child.stdin.as_mut().unwrap();
-        stdin.write_all(data.as_bytes())
+        self.writer().write_all(data.as_bytes())
+    }
+
+    /// Returns the stream used to write to the child.
+    fn writer(&mut self) -> &mut dyn Write {
+        match self.writer {
+            Writer::Piped(ref mut stdin) => stdin,
+            Writer::Pty(ref mut master) => master,
+        }
     }
 
     /// Searches for some marker in data from the subprocess.
@@ -79,6 +244,11 @@ impl Process {
     /// matched, for later processing/matching by callers, without having to keep a buffer around
     /// after the call.
     ///
+    /// A background thread continuously drains the child's output into a shared buffer, so
+    /// `expect` always sees everything the child has written since the last call, not just
+    /// whatever arrived while this particular call was running. On a match, only the bytes up to
+    /// and including the match are consumed; anything after it stays buffered for the next call.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -111,24 +281,18 @@ impl Process {
     /// p.expect(vec!["test script"], Duration::from_secs(1)).unwrap();
     /// ```
     ///
-    /// Note the need to explicitly wait for the `test.sh` prompt again - this is because
-    /// reckon literally reads all data that comes from the program, and skips nothing.
-    /// This is somewhat of a departure from how programs like pexpect work, given that they
-    /// feed a buffer continuously from a background thread, and match/clear that for expect()
-    /// calls.
-    ///
     /// ```rust
     /// # use reckon::base::{Process, Duration};
     /// # let mut p = Process::new("bash", vec!["test.sh"]).unwrap();
     /// # p.expect(vec!["!"], Duration::from_secs(2)).unwrap();
     /// # p.emit("test\n").unwrap();
     /// # p.expect(vec!["test script"], Duration::from_secs(1)).unwrap();
-    /// p.expect(vec!["!"], Duration::from_secs(1)).unwrap();
     /// p.emit("commit synchronize\n").unwrap();
     /// ```
     ///
-    /// // Multiple matches are possible, and when this happens, the first one to match will
-    /// // be returned, and the input stream will be stopped.
+    /// // Multiple matches are possible, and when this happens, the first needle in the list to
+    /// // match will be returned, and only its matched bytes (and anything before them) are
+    /// // consumed from the buffer.
     ///
     /// ```rust
     /// # use reckon::base::{Process, Duration};
@@ -145,34 +309,130 @@ impl Process {
                   needles: Vec<&str>,
                   timeout: Duration)
                   -> Result<(usize, String), Error> {
-        let start_time = Instant::now();
+        expect_on(&self.buffer, needles, timeout, self.strip_ansi)
+    }
 
-        let stdout = self.child.stdout.as_mut().unwrap();
-        let rs = RegexSet::new(&needles).unwrap();
+    /// Like [expect](#method.expect), but matches only against the child's stderr rather than
+    /// its stdout.
+    ///
+    /// Stderr is always captured into its own buffer, independent of
+    /// [set_merge_stderr](#method.set_merge_stderr) - that toggle only controls whether stderr is
+    /// *also* fed into the buffer `expect` scans.
+    ///
+    /// Returns an error in pty mode, where stdout and stderr are already the same stream (both
+    /// ends up on the pty), so there's nothing separate to match against; use `expect` instead.
+    pub fn expect_stderr(&mut self,
+                          needles: Vec<&str>,
+                          timeout: Duration)
+                          -> Result<(usize, String), Error> {
+        match self.stderr {
+            Some(ref stderr) => expect_on(stderr, needles, timeout, self.strip_ansi),
+            None => Err(Error::other("no separate stderr stream in pty mode; use expect() instead")),
+        }
+    }
+
+    /// Waits for the child's output stream to reach end-of-stream - i.e. the child closed
+    /// stdout/the pty, typically because it exited.
+    ///
+    /// This is distinct from the timeout `expect` returns on a failed match: `expect_eof`
+    /// succeeds once nothing more will ever arrive from the child. Bytes already buffered but not
+    /// yet consumed by `expect` are left in place.
+    ///
+    /// ```rust
+    /// # use reckon::base::{Process, Duration};
+    /// let mut p = Process::new("true", vec![]).unwrap();
+    /// p.expect_eof(Duration::from_secs(1)).unwrap();
+    /// ```
+    pub fn expect_eof(&mut self, timeout: Duration) -> Result<(), Error> {
+        let start_time = Instant::now();
+        let (lock, cv) = &*self.buffer;
+        let mut state = lock.lock().unwrap();
 
-        let mut b = String::new();
-        let mut c = stdout.chars();
         loop {
-            let e = start_time.elapsed();
-            if e >= timeout {
-                break;
+            if state.eof {
+                return Ok(());
             }
 
-            // Skip any UTF-8 decoding errors in the stream.
-            match c.next() {
-                Some(ch) => match ch {
-                    Ok(p) => b.push(p),
-                    Err(_) => continue,
-                },
-                None => continue,
+            let elapsed = start_time.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::new(ErrorKind::TimedOut, "child has not reached EOF"));
             }
 
-            for n in rs.matches(&b).into_iter() {
-                return Ok((n, b));
-            }
+            let (guard, _) = cv.wait_timeout(state, timeout - elapsed).unwrap();
+            state = guard;
+        }
+    }
+
+    /// Blocks until the child exits, then returns its exit status.
+    ///
+    /// On Unix, a status whose process was killed by a signal rather than exiting normally can
+    /// be inspected via [`ExitStatusExt::signal`](trait.ExitStatusExt.html#tymethod.signal),
+    /// re-exported from this module.
+    ///
+    /// ```rust
+    /// # use reckon::base::Process;
+    /// let mut p = Process::new("true", vec![]).unwrap();
+    /// let status = p.wait().unwrap();
+    /// assert!(status.success());
+    /// ```
+    pub fn wait(&mut self) -> Result<ExitStatus, Error> {
+        self.child.wait()
+    }
+
+    /// Checks whether the child has exited yet without blocking.
+    ///
+    /// Returns `Ok(None)` if it's still running.
+    pub fn status(&mut self) -> Result<Option<ExitStatus>, Error> {
+        self.child.try_wait()
+    }
+
+    /// Sends a Unix signal directly to the child process.
+    ///
+    /// ```rust
+    /// # use reckon::base::Process;
+    /// let mut p = Process::new("cat", vec![]).unwrap();
+    /// p.send_signal(libc::SIGTERM).unwrap();
+    /// ```
+    pub fn send_signal(&mut self, sig: libc::c_int) -> Result<(), Error> {
+        let pid = self.child.id() as libc::pid_t;
+        let rc = unsafe { libc::kill(pid, sig) };
+        if rc != 0 {
+            return Err(Error::last_os_error());
         }
+        Ok(())
+    }
+
+    /// Sends `SIGINT` to the child, as if someone had pressed `Ctrl-C` at its terminal.
+    pub fn interrupt(&mut self) -> Result<(), Error> {
+        self.send_signal(libc::SIGINT)
+    }
+
+    /// Sends `SIGTERM` to the child, requesting a graceful shutdown.
+    pub fn terminate(&mut self) -> Result<(), Error> {
+        self.send_signal(libc::SIGTERM)
+    }
 
-        return Err(Error::new(ErrorKind::TimedOut, b));
+    /// Writes the control byte for `c` (e.g. `'c'` for `Ctrl-C`, `'d'` for `Ctrl-D`) to the
+    /// child's stdin, the way a terminal driver would translate the key combination.
+    ///
+    /// This is delivered as input data, not a signal - it only has signal-like effects (like
+    /// interrupting the foreground process) when the child's tty has that translation enabled,
+    /// which is the normal case for a [pty-backed](#method.new_pty) process. Prefer
+    /// [interrupt](#method.interrupt)/[terminate](#method.terminate) when you want to guarantee
+    /// delivery regardless of tty settings.
+    ///
+    /// ```rust
+    /// # use reckon::base::Process;
+    /// let mut p = Process::new_pty("cat", vec![]).unwrap();
+    /// p.send_control('c').unwrap();
+    /// ```
+    pub fn send_control(&mut self, c: char) -> Result<(), Error> {
+        if !c.is_ascii_alphabetic() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                   "send_control only accepts ASCII letters"));
+        }
+        let byte = (c.to_ascii_uppercase() as u8) - b'A' + 1;
+        self.writer().write_all(&[byte])
     }
 }
 
@@ -180,8 +440,247 @@ impl Drop for Process {
     /// Destructor to automatically clean up the subprocess.
     ///
     /// This prevents the child process sticking around when the parent dies, which apparently can
-    /// happen when you capture all `std{io,err,out}` pipes.
+    /// happen when you capture all `std{io,err,out}` pipes. The pty master/writer fd (if any) is
+    /// closed as part of dropping `self.writer`; we still need to reap the child ourselves so it
+    /// doesn't linger as a zombie, but only `kill` it if it hasn't already exited on its own - a
+    /// caller who already did `expect_eof`/`wait` may have legitimately let it run to completion.
+    /// The background reader thread notices the child's output stream close and winds itself
+    /// down on its own.
     fn drop(&mut self) {
-        self.child.kill().expect("could not kill the process!");
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
     }
 }
+
+/// Scans `buffer` for the first of `needles` (in list order) to appear, blocking (up to
+/// `timeout`) on the background reader for more data when nothing matches yet.
+///
+/// Shared by [Process::expect](struct.Process.html#method.expect) and
+/// [Process::expect_stderr](struct.Process.html#method.expect_stderr), which differ only in
+/// which buffer they scan.
+fn expect_on(buffer: &Arc<(Mutex<Buffer>, Condvar)>,
+             needles: Vec<&str>,
+             timeout: Duration,
+             strip_ansi: bool)
+             -> Result<(usize, String), Error> {
+    let start_time = Instant::now();
+    let patterns: Vec<Regex> = needles.iter().map(|n| Regex::new(n).unwrap()).collect();
+
+    let (lock, cv) = &**buffer;
+    let mut state = lock.lock().unwrap();
+
+    loop {
+        // Decode whatever's valid and skip any bad bytes, the same way the original `chars()`
+        // based implementation did, rather than requiring the *whole* buffer to be valid UTF-8 -
+        // terminal programs routinely interleave stray non-UTF-8 bytes with otherwise-matchable
+        // text, and refusing to scan at all because of one bad byte elsewhere in the buffer
+        // would make `expect` time out even though the needle is already sitting there.
+        let scanned = if strip_ansi {
+            let (filtered, strip_raw_end) = strip_ansi_sequences(&state.bytes);
+            let (text, decode_raw_end) = decode_lossy(&filtered);
+            find_first_match(&patterns, &text).map(|(i, consumed)| {
+                let filtered_consumed = if consumed == 0 { 0 } else { decode_raw_end[consumed - 1] };
+                let raw_consumed = if filtered_consumed == 0 { 0 } else { strip_raw_end[filtered_consumed - 1] };
+                (i, text[..consumed].to_owned(), raw_consumed)
+            })
+        } else {
+            let (text, raw_end) = decode_lossy(&state.bytes);
+            find_first_match(&patterns, &text).map(|(i, consumed)| {
+                let raw_consumed = if consumed == 0 { 0 } else { raw_end[consumed - 1] };
+                (i, text[..consumed].to_owned(), raw_consumed)
+            })
+        };
+
+        if let Some((i, matched, raw_consumed)) = scanned {
+            state.bytes.drain(..raw_consumed);
+            return Ok((i, matched));
+        }
+
+        if state.eof {
+            let leftover = String::from_utf8_lossy(&state.bytes).into_owned();
+            return Err(Error::new(ErrorKind::UnexpectedEof, leftover));
+        }
+
+        let elapsed = start_time.elapsed();
+        if elapsed >= timeout {
+            let leftover = String::from_utf8_lossy(&state.bytes).into_owned();
+            return Err(Error::new(ErrorKind::TimedOut, leftover));
+        }
+
+        let (guard, _) = cv.wait_timeout(state, timeout - elapsed).unwrap();
+        state = guard;
+    }
+}
+
+/// Decodes as much of `raw` as is valid UTF-8, returning the decoded text plus, for each decoded
+/// byte, the offset in `raw` immediately following it (same scheme as
+/// [strip_ansi_sequences](fn.strip_ansi_sequences.html)'s return value, so the two compose).
+///
+/// Invalid bytes are skipped rather than aborting the whole decode, matching the original
+/// `chars()`-based implementation's "skip any UTF-8 decoding errors in the stream" behavior. An
+/// incomplete sequence trailing at the end of `raw` (more bytes needed, not actually invalid) is
+/// left undecoded for next time rather than being treated as bad.
+fn decode_lossy(raw: &[u8]) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut raw_end = Vec::with_capacity(raw.len());
+
+    let mut i = 0;
+    while i < raw.len() {
+        match std::str::from_utf8(&raw[i..]) {
+            Ok(valid) => {
+                for k in 0..valid.len() {
+                    raw_end.push(i + k + 1);
+                }
+                text.push_str(valid);
+                break;
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                if valid_up_to > 0 {
+                    let valid = std::str::from_utf8(&raw[i..i + valid_up_to]).unwrap();
+                    for k in 0..valid.len() {
+                        raw_end.push(i + k + 1);
+                    }
+                    text.push_str(valid);
+                }
+
+                match e.error_len() {
+                    // A genuinely invalid byte (or bytes) - drop it and keep decoding the rest.
+                    Some(bad_len) => i += valid_up_to + bad_len,
+                    // The tail looks like the start of a multi-byte sequence that just hasn't
+                    // fully arrived yet; stop here and wait for more data.
+                    None => break,
+                }
+            }
+        }
+    }
+
+    (text, raw_end)
+}
+
+/// Returns the index of the first needle (in list order) that matches `text`, along with the
+/// byte offset its match ends at.
+fn find_first_match(patterns: &[Regex], text: &str) -> Option<(usize, usize)> {
+    patterns.iter()
+        .enumerate()
+        .find_map(|(i, pattern)| pattern.find(text).map(|mat| (i, mat.end())))
+}
+
+/// Strips ANSI/CSI escape sequences out of `raw`, returning the filtered bytes plus, for each
+/// filtered byte, the offset in `raw` immediately following it - so a match position found in
+/// the filtered bytes can be translated back into how many raw bytes to drain from the buffer.
+///
+/// Handles the common `ESC [ params... final` (CSI) form as well as a bare `ESC` followed by a
+/// single character. Escape bytes never occur as continuation bytes of a multi-byte UTF-8
+/// sequence, so this can scan `raw` byte-by-byte without decoding it.
+fn strip_ansi_sequences(raw: &[u8]) -> (Vec<u8>, Vec<usize>) {
+    let mut filtered = Vec::with_capacity(raw.len());
+    let mut raw_end = Vec::with_capacity(raw.len());
+
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == 0x1B {
+            i += 1;
+            if raw.get(i) == Some(&b'[') {
+                i += 1;
+                while i < raw.len() && !(0x40..=0x7E).contains(&raw[i]) {
+                    i += 1;
+                }
+                if i < raw.len() {
+                    i += 1;
+                }
+            } else if i < raw.len() {
+                i += 1;
+            }
+            continue;
+        }
+
+        filtered.push(raw[i]);
+        i += 1;
+        raw_end.push(i);
+    }
+
+    (filtered, raw_end)
+}
+
+/// Allocates a fresh, empty buffer/condvar pair for a background reader to fill.
+fn new_buffer() -> Arc<(Mutex<Buffer>, Condvar)> {
+    Arc::new((Mutex::new(Buffer { bytes: Vec::new(), eof: false }), Condvar::new()))
+}
+
+/// Spawns the background thread that continuously drains `stream` into a shared buffer, and
+/// returns the buffer/condvar pair `expect` scans and waits on.
+fn spawn_reader<R>(mut stream: R) -> Arc<(Mutex<Buffer>, Condvar)>
+    where R: Read + Send + 'static
+{
+    let shared = new_buffer();
+    let reader_shared = shared.clone();
+
+    thread::spawn(move || {
+        let (lock, cv) = &*reader_shared;
+        let mut chunk = [0u8; READ_CHUNK];
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => {
+                    lock.lock().unwrap().eof = true;
+                    cv.notify_all();
+                    break;
+                }
+                Ok(n) => {
+                    lock.lock().unwrap().bytes.extend_from_slice(&chunk[..n]);
+                    cv.notify_all();
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    lock.lock().unwrap().eof = true;
+                    cv.notify_all();
+                    break;
+                }
+            }
+        }
+    });
+
+    shared
+}
+
+/// Spawns the background thread that drains a stderr `stream` into its own buffer (`own`), and,
+/// whenever `merge` is enabled, mirrors the same bytes into `primary` so `expect` can see them
+/// too. `own` always gets every byte regardless of `merge`, so `expect_stderr` keeps working even
+/// when merge mode is off.
+fn spawn_stderr_reader<R>(mut stream: R,
+                          own: Arc<(Mutex<Buffer>, Condvar)>,
+                          primary: Arc<(Mutex<Buffer>, Condvar)>,
+                          merge: Arc<AtomicBool>)
+    where R: Read + Send + 'static
+{
+    thread::spawn(move || {
+        let (own_lock, own_cv) = &*own;
+        let (primary_lock, primary_cv) = &*primary;
+        let mut chunk = [0u8; READ_CHUNK];
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => {
+                    own_lock.lock().unwrap().eof = true;
+                    own_cv.notify_all();
+                    break;
+                }
+                Ok(n) => {
+                    own_lock.lock().unwrap().bytes.extend_from_slice(&chunk[..n]);
+                    own_cv.notify_all();
+                    if merge.load(Ordering::Relaxed) {
+                        primary_lock.lock().unwrap().bytes.extend_from_slice(&chunk[..n]);
+                        primary_cv.notify_all();
+                    }
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    own_lock.lock().unwrap().eof = true;
+                    own_cv.notify_all();
+                    break;
+                }
+            }
+        }
+    });
+}
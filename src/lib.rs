@@ -0,0 +1,7 @@
+//! `reckon` is a small, blocking "expect"-style library for scripting interactions with
+//! subprocesses.
+//!
+//! [`base`] has the low-level `Process` type; [`repl`] builds REPL/prompt-session ergonomics on
+//! top of it for the common case of driving a shell or other prompt-driven program.
+pub mod base;
+pub mod repl;
@@ -0,0 +1,89 @@
+//! A REPL/prompt-session layer built on top of `base::Process`.
+//!
+//! This is the `session.execute(cmd)` ergonomics that would otherwise have to be hand-rolled on
+//! top of the raw `emit`/`expect` API every time someone wants to drive a shell or other
+//! prompt-driven program.
+use std::io::Error;
+use std::time::Duration;
+
+use crate::base::Process;
+
+/// How long to give the child to exit on its own after sending the quit command, before the
+/// underlying [`Process`]'s own `Drop` falls back to killing it.
+const QUIT_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// Wraps a [`Process`](../base/struct.Process.html) with the bookkeeping common to shell-style
+/// prompt automation: a prompt pattern to wait for, an optional command to send on shutdown, and
+/// whether the child echoes input back (most ttys do).
+pub struct ReplSession {
+    process: Process,
+    prompt: String,
+    quit_command: Option<String>,
+    echo: bool,
+}
+
+impl ReplSession {
+    /// Wraps an already-spawned `process` as a REPL session.
+    ///
+    /// `prompt` is the pattern `expect_prompt`/`execute` wait for. `echo` should be `true` when
+    /// the child is attached to a tty (e.g. via
+    /// [`Process::new_pty`](../base/struct.Process.html#method.new_pty)) and therefore echoes
+    /// back whatever is written to it.
+    pub fn new(process: Process, prompt: &str, echo: bool) -> ReplSession {
+        ReplSession {
+            process,
+            prompt: prompt.to_owned(),
+            quit_command: None,
+            echo,
+        }
+    }
+
+    /// Sets the command sent to the child when the session is dropped/closed, instead of just
+    /// killing it outright - e.g. `"exit"` for a shell, or `"quit"` for an ftp client.
+    pub fn set_quit_command(&mut self, command: &str) {
+        self.quit_command = Some(command.to_owned());
+    }
+
+    /// Waits for the stored prompt to appear in the child's output.
+    pub fn expect_prompt(&mut self, timeout: Duration) -> Result<String, Error> {
+        let (_, matched) = self.process.expect(vec![self.prompt.as_str()], timeout)?;
+        Ok(matched)
+    }
+
+    /// Sends `cmd` followed by a newline, then waits for the next prompt and returns everything
+    /// printed up to and including that prompt.
+    ///
+    /// On an echoing terminal the command we just sent shows up in the child's own output before
+    /// its real response does, so this waits twice: first for the echoed command text, to skip
+    /// past it, and then for the actual prompt that follows the command's output.
+    pub fn execute(&mut self, cmd: &str, timeout: Duration) -> Result<String, Error> {
+        self.process.emit(&format!("{}\n", cmd))?;
+
+        if self.echo {
+            self.process.expect(vec![&regex::escape(cmd)], timeout)?;
+        }
+
+        self.expect_prompt(timeout)
+    }
+
+    /// Closes the session, sending the quit command (if any) instead of hard-killing the child.
+    ///
+    /// This just hands off to `Drop`; it exists so callers have an explicit, named way to wind
+    /// a session down rather than relying on scope exit.
+    pub fn close(self) {}
+}
+
+impl Drop for ReplSession {
+    /// Sends the quit command (if any) and gives the child a grace period to act on it and exit
+    /// on its own, before the underlying `Process`'s own `Drop` falls back to killing it.
+    ///
+    /// Without this wait, `Process::drop` would run immediately afterwards, see the child still
+    /// running (it hasn't had a scheduler slice to even read the command yet) and hard-kill it -
+    /// silently defeating the point of a quit command.
+    fn drop(&mut self) {
+        if let Some(ref quit) = self.quit_command {
+            let _ = self.process.emit(&format!("{}\n", quit));
+            let _ = self.process.expect_eof(QUIT_GRACE_PERIOD);
+        }
+    }
+}